@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use utils::{EntityName, IntentName, SlotName};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NluEngineModel {
+    pub dataset_metadata: DatasetMetadata,
+    pub intent_parsers: Vec<String>,
+    pub builtin_entity_parser: String,
+    pub custom_entity_parser: String,
+    pub model_version: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatasetMetadata {
+    pub language_code: String,
+    pub entities: HashMap<EntityName, Entity>,
+    pub slot_name_mappings: HashMap<IntentName, HashMap<SlotName, EntityName>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Entity {
+    pub automatically_extensible: bool,
+    /// Optional conversion applied to the resolved value of the custom entity.
+    /// Absent in models that predate typed values, hence defaulted.
+    #[serde(default)]
+    pub value_type: Option<ValueType>,
+}
+
+/// Declared target type of a custom entity's resolved value. `Bytes` keeps the
+/// value as the raw string (the default) while the other variants request a
+/// typed coercion during slot resolution.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind")]
+pub enum ValueType {
+    Bytes,
+    Integer,
+    Float,
+    Boolean,
+    Timestamp,
+    TimestampFmt { format: String },
+    TimestampTZFmt { format: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelVersion {
+    pub model_version: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProcessingUnitMetadata {
+    pub unit_name: String,
+}
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::HashSet;
 use std::fs;
 use std::io;
@@ -11,13 +12,18 @@ use errors::*;
 use failure::ResultExt;
 use intent_parser::*;
 use itertools::Itertools;
-use models::{DatasetMetadata, Entity, ModelVersion, NluEngineModel, ProcessingUnitMetadata};
+use models::{DatasetMetadata, Entity, ModelVersion, NluEngineModel, ProcessingUnitMetadata, ValueType};
 use nlu_utils::string::substring_with_char_range;
 use resources::loading::load_shared_resources;
 use resources::SharedResources;
 use slot_utils::*;
 use serde_json;
-use snips_nlu_ontology::{BuiltinEntityKind, IntentParserResult, Language, Slot, SlotValue};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use snips_nlu_ontology::{
+    BuiltinEntityKind, Grain, InstantTimeValue, IntentClassifierResult, IntentParserResult,
+    Language, NumberValue, Precision, Slot, SlotValue,
+};
 use tempfile;
 use utils::{EntityName, IntentName, SlotName, extract_nlu_engine_zip_archive};
 
@@ -25,10 +31,15 @@ pub struct SnipsNluEngine {
     dataset_metadata: DatasetMetadata,
     intent_parsers: Vec<Box<IntentParser>>,
     shared_resources: Arc<SharedResources>,
+    default_timezone: Tz,
 }
 
 impl SnipsNluEngine {
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_path_with_tz(path, Tz::UTC)
+    }
+
+    pub fn from_path_with_tz<P: AsRef<Path>>(path: P, default_timezone: Tz) -> Result<Self> {
         let model = SnipsNluEngine::load_model(&path)?;
 
         let language = Language::from_str(&model.dataset_metadata.language_code)?;
@@ -46,6 +57,7 @@ impl SnipsNluEngine {
             dataset_metadata: model.dataset_metadata,
             intent_parsers: parsers,
             shared_resources,
+            default_timezone,
         })
     }
 
@@ -111,18 +123,41 @@ impl SnipsNluEngine {
             dataset_metadata: model.dataset_metadata,
             intent_parsers: parsers,
             shared_resources,
+            default_timezone: Tz::UTC,
         })
     }
 }
 
 impl SnipsNluEngine {
     pub fn from_zip<R: io::Read + io::Seek>(reader: R) -> Result<Self> {
+        Self::from_zip_with_tz(reader, Tz::UTC)
+    }
+
+    pub fn from_zip_with_tz<R: io::Read + io::Seek>(
+        reader: R,
+        default_timezone: Tz,
+    ) -> Result<Self> {
         let temp_dir = tempfile::Builder::new()
             .prefix("temp_dir_nlu_")
             .tempdir()?;
         let temp_dir_path = temp_dir.path();
         let engine_dir_path = extract_nlu_engine_zip_archive(reader, temp_dir_path)?;
-        Ok(SnipsNluEngine::from_path(engine_dir_path)?)
+        SnipsNluEngine::from_path_with_tz(engine_dir_path, default_timezone)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::from_bytes_with_tz(bytes, Tz::UTC)
+    }
+
+    /// Builds an engine from a model archive already held in memory.
+    ///
+    /// The bytes are expanded into a temporary directory before loading: the
+    /// shared-resource and per-parser loaders in `resources::loading` and
+    /// `intent_parser` read their artifacts directly from the filesystem, so a
+    /// fully temp-dir-free path would require routing those loaders through an
+    /// abstraction they do not currently expose.
+    pub fn from_bytes_with_tz(bytes: &[u8], default_timezone: Tz) -> Result<Self> {
+        SnipsNluEngine::from_zip_with_tz(io::Cursor::new(bytes), default_timezone)
     }
 }
 
@@ -202,6 +237,7 @@ impl SnipsNluEngine {
                     &entity,
                     &custom_entities,
                     self.shared_resources.custom_entity_parser.clone())?
+                    .map(|resolved_slot| coerce_custom_slot(resolved_slot, entity, self.default_timezone))
             } else {
                 resolve_builtin_slot(
                     slot,
@@ -216,6 +252,51 @@ impl SnipsNluEngine {
     }
 }
 
+impl SnipsNluEngine {
+    /// Returns every declared intent ranked by decreasing confidence.
+    ///
+    /// The `IntentParser` trait only exposes `parse`, which yields the single
+    /// best intent together with its confidence score. Rather than fabricating a
+    /// full score distribution, the ranking surfaces that winner with its
+    /// reported score and lists the remaining declared intents with a zero
+    /// score. When no parser matches, `parse` reports a `None` intent, which
+    /// stands for the implicit null intent and leaves only the zero-scored
+    /// candidates in the ranking.
+    pub fn get_intents(&self, input: &str) -> Result<Vec<IntentClassifierResult>> {
+        let best_intent = self.parse(input, None)?.intent;
+        let best_intent_name = best_intent.as_ref().map(|res| res.intent_name.clone());
+
+        let mut results = Vec::new();
+        if let Some(res) = best_intent {
+            results.push(res);
+        }
+        for intent_name in self.dataset_metadata.slot_name_mappings.keys() {
+            if best_intent_name.as_ref().map_or(true, |name| name != intent_name) {
+                results.push(IntentClassifierResult {
+                    intent_name: intent_name.to_string(),
+                    confidence_score: 0.,
+                });
+            }
+        }
+        results.sort_by(|left, right| {
+            right
+                .confidence_score
+                .partial_cmp(&left.confidence_score)
+                .unwrap_or(Ordering::Equal)
+        });
+        Ok(results)
+    }
+
+    /// Resolves the slots of `input` for an arbitrary chosen `intent`, reusing
+    /// the standard `parse` resolution path restricted to that single intent.
+    pub fn get_slots(&self, input: &str, intent: &str) -> Result<Vec<Slot>> {
+        Ok(self
+            .parse(input, Some(&[intent.to_string()]))?
+            .slots
+            .unwrap_or_else(Vec::new))
+    }
+}
+
 impl SnipsNluEngine {
     pub fn extract_slot(
         &self,
@@ -237,6 +318,7 @@ impl SnipsNluEngine {
                 slot_name.to_string(),
                 custom_entity,
                 self.shared_resources.custom_entity_parser.clone())?
+                .map(|slot| coerce_custom_slot(slot, custom_entity, self.default_timezone))
         } else {
             extract_builtin_slot(
                 input,
@@ -248,6 +330,73 @@ impl SnipsNluEngine {
     }
 }
 
+/// Applies the entity's declared `value_type` conversion to a freshly resolved
+/// custom slot. Only the raw `SlotValue::Custom` string produced by
+/// `resolve_custom_slot` or `extract_custom_slot` is touched; on conversion failure the original string
+/// is kept and the failure is reported through the debug log.
+fn coerce_custom_slot(mut slot: Slot, entity: &Entity, default_timezone: Tz) -> Slot {
+    let value_type = match entity.value_type.as_ref() {
+        Some(value_type) if *value_type != ValueType::Bytes => value_type,
+        _ => return slot,
+    };
+    if let SlotValue::Custom(ref custom_value) = slot.value {
+        match coerce_custom_value(&custom_value.value, value_type, default_timezone) {
+            Some(coerced) => {
+                slot.value = coerced;
+                return slot;
+            }
+            None => debug!(
+                "Could not coerce custom slot value '{}' to {:?}, keeping raw string",
+                &custom_value.value, value_type
+            ),
+        }
+    }
+    slot
+}
+
+fn coerce_custom_value(raw: &str, value_type: &ValueType, default_timezone: Tz) -> Option<SlotValue> {
+    let trimmed = raw.trim();
+    match *value_type {
+        ValueType::Bytes => None,
+        ValueType::Integer => i64::from_str(trimmed)
+            .ok()
+            .map(|value| SlotValue::Number(NumberValue { value: value as f64 })),
+        ValueType::Float => f64::from_str(trimmed)
+            .ok()
+            .map(|value| SlotValue::Number(NumberValue { value })),
+        // `snips_nlu_ontology` has no dedicated boolean slot value, so the parsed
+        // boolean is surfaced as a normalized custom string.
+        ValueType::Boolean => match trimmed.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Some(SlotValue::Custom("true".to_string().into())),
+            "false" | "no" | "off" | "0" => Some(SlotValue::Custom("false".to_string().into())),
+            _ => None,
+        },
+        ValueType::Timestamp => DateTime::parse_from_rfc3339(trimmed)
+            .ok()
+            .map(|datetime| instant_time_value(datetime.to_rfc3339())),
+        // The format carries no timezone: the parsed naive datetime is anchored
+        // in the engine's default timezone before being normalized to UTC.
+        ValueType::TimestampFmt { ref format } => NaiveDateTime::parse_from_str(trimmed, format)
+            .ok()
+            .and_then(|naive| default_timezone.from_local_datetime(&naive).single())
+            .map(|datetime| instant_time_value(datetime.with_timezone(&Utc).to_rfc3339())),
+        // The format carries an explicit offset (e.g. a `%z` token): the parsed
+        // instant keeps its original offset, which already encodes a UTC-aligned
+        // point in time.
+        ValueType::TimestampTZFmt { ref format } => DateTime::parse_from_str(trimmed, format)
+            .ok()
+            .map(|datetime| instant_time_value(datetime.to_rfc3339())),
+    }
+}
+
+fn instant_time_value(value: String) -> SlotValue {
+    SlotValue::InstantTime(InstantTimeValue {
+        value,
+        grain: Grain::Second,
+        precision: Precision::Exact,
+    })
+}
+
 fn extract_custom_slot(
     input: String,
     entity_name: EntityName,
@@ -300,6 +449,8 @@ fn extract_builtin_slot(
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
     use snips_nlu_ontology::NumberValue;
     use super::*;
     use entity_parser::custom_entity_parser::CustomEntity;
@@ -353,6 +504,41 @@ mod tests {
         assert_eq!(expected_slots, result.slots);
     }
 
+    #[test]
+    fn from_bytes_works() {
+        // Given
+        let path = file_path("tests")
+            .join("models")
+            .join("nlu_engine.zip");
+        let bytes = fs::read(path).unwrap();
+
+        // When
+        let nlu_engine = SnipsNluEngine::from_bytes(&bytes);
+
+        // Then
+        assert!(nlu_engine.is_ok());
+
+        let result = nlu_engine
+            .unwrap()
+            .parse("Make me two cups of coffee please", None)
+            .unwrap();
+
+        let expected_entity_value = SlotValue::Number(NumberValue { value: 2.0 });
+        let expected_slots = Some(vec![
+            Slot {
+                raw_value: "two".to_string(),
+                value: expected_entity_value,
+                range: Some(8..11),
+                entity: "snips/number".to_string(),
+                slot_name: "number_of_cups".to_string(),
+            },
+        ]);
+        let expected_intent = Some("MakeCoffee".to_string());
+
+        assert_eq!(expected_intent, result.intent.map(|intent| intent.intent_name));
+        assert_eq!(expected_slots, result.slots);
+    }
+
     #[test]
     fn parse_works() {
         // Given
@@ -383,6 +569,58 @@ mod tests {
         assert_eq!(expected_slots, result.slots);
     }
 
+    #[test]
+    fn should_get_intents() {
+        // Given
+        let path = file_path("tests")
+            .join("models")
+            .join("nlu_engine");
+        let nlu_engine = SnipsNluEngine::from_path(path).unwrap();
+
+        // When
+        let results = nlu_engine
+            .get_intents("Make me two cups of coffee please")
+            .unwrap();
+
+        // Then
+        let intents = results
+            .iter()
+            .map(|res| res.intent_name.clone())
+            .collect::<Vec<_>>();
+        assert!(intents.contains(&"MakeCoffee".to_string()));
+        assert_eq!("MakeCoffee".to_string(), results[0].intent_name);
+        // The ranking is sorted by decreasing confidence.
+        for pair in results.windows(2) {
+            assert!(pair[0].confidence_score >= pair[1].confidence_score);
+        }
+    }
+
+    #[test]
+    fn should_get_slots() {
+        // Given
+        let path = file_path("tests")
+            .join("models")
+            .join("nlu_engine");
+        let nlu_engine = SnipsNluEngine::from_path(path).unwrap();
+
+        // When
+        let slots = nlu_engine
+            .get_slots("Make me two cups of coffee please", "MakeCoffee")
+            .unwrap();
+
+        // Then
+        let expected_slots = vec![
+            Slot {
+                raw_value: "two".to_string(),
+                value: SlotValue::Number(NumberValue { value: 2.0 }),
+                range: Some(8..11),
+                entity: "snips/number".to_string(),
+                slot_name: "number_of_cups".to_string(),
+            },
+        ];
+        assert_eq!(expected_slots, slots);
+    }
+
     #[test]
     fn should_extract_custom_slot_when_tagged() {
         // Given
@@ -391,6 +629,7 @@ mod tests {
         let slot_name = "slot".to_string();
         let custom_entity = Entity {
             automatically_extensible: true,
+            value_type: None,
         };
 
         let mocked_custom_parser = Arc::new(MockedCustomEntityParser::from_iter(
@@ -436,6 +675,7 @@ mod tests {
         let slot_name = "slot".to_string();
         let custom_entity = Entity {
             automatically_extensible: true,
+            value_type: None,
         };
 
         let mocked_custom_parser = Arc::new(MockedCustomEntityParser::from_iter(vec![]));
@@ -463,6 +703,7 @@ mod tests {
         let slot_name = "slot".to_string();
         let custom_entity = Entity {
             automatically_extensible: false,
+            value_type: None,
         };
 
         let mocked_custom_parser = Arc::new(MockedCustomEntityParser::from_iter(vec![]));
@@ -475,4 +716,83 @@ mod tests {
         let expected_slot = None;
         assert_eq!(expected_slot, extracted_slot);
     }
+
+    #[test]
+    fn should_coerce_custom_value() {
+        // Given / When / Then
+        assert_eq!(
+            Some(SlotValue::Number(NumberValue { value: 42.0 })),
+            coerce_custom_value("42", &ValueType::Integer, Tz::UTC)
+        );
+        assert_eq!(
+            Some(SlotValue::Number(NumberValue { value: 4.5 })),
+            coerce_custom_value(" 4.5 ", &ValueType::Float, Tz::UTC)
+        );
+        assert_eq!(
+            Some(SlotValue::Custom("true".to_string().into())),
+            coerce_custom_value("Yes", &ValueType::Boolean, Tz::UTC)
+        );
+        assert_eq!(
+            Some(SlotValue::InstantTime(InstantTimeValue {
+                value: "2018-01-01T12:00:00+00:00".to_string(),
+                grain: Grain::Second,
+                precision: Precision::Exact,
+            })),
+            coerce_custom_value("2018-01-01T12:00:00Z", &ValueType::Timestamp, Tz::UTC)
+        );
+    }
+
+    #[test]
+    fn should_coerce_custom_value_with_timezone() {
+        // Given
+        let naive_fmt = ValueType::TimestampFmt {
+            format: "%Y-%m-%d %H:%M:%S".to_string(),
+        };
+        let tz_fmt = ValueType::TimestampTZFmt {
+            format: "%Y-%m-%d %H:%M:%S %z".to_string(),
+        };
+
+        // When / Then: a naive format is interpreted in the default timezone and
+        // normalized to UTC (Paris is UTC+1 in winter).
+        assert_eq!(
+            Some(SlotValue::InstantTime(InstantTimeValue {
+                value: "2018-01-01T11:00:00+00:00".to_string(),
+                grain: Grain::Second,
+                precision: Precision::Exact,
+            })),
+            coerce_custom_value("2018-01-01 12:00:00", &naive_fmt, Tz::Europe__Paris)
+        );
+
+        // A format carrying an explicit offset keeps that offset.
+        assert_eq!(
+            Some(SlotValue::InstantTime(InstantTimeValue {
+                value: "2018-01-01T12:00:00+02:00".to_string(),
+                grain: Grain::Second,
+                precision: Precision::Exact,
+            })),
+            coerce_custom_value("2018-01-01 12:00:00 +0200", &tz_fmt, Tz::UTC)
+        );
+    }
+
+    #[test]
+    fn should_keep_raw_string_on_coercion_failure() {
+        // Given
+        let slot = Slot {
+            raw_value: "not a number".to_string(),
+            value: SlotValue::Custom("not a number".to_string().into()),
+            range: Some(0..12),
+            entity: "entity".to_string(),
+            slot_name: "slot".to_string(),
+        };
+        let entity = Entity {
+            automatically_extensible: true,
+            value_type: Some(ValueType::Integer),
+        };
+
+        // When
+        let coerced = coerce_custom_slot(slot.clone(), &entity, Tz::UTC);
+
+        // Then
+        assert_eq!(slot, coerced);
+    }
 }